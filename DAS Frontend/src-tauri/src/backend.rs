@@ -0,0 +1,550 @@
+// Cross-platform backend process lifecycle: spawn, locate the sidecar,
+// stream its output, and terminate it gracefully.
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::sync::MutexGuard;
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Default grace window given to the backend to shut down on its own
+/// before `shutdown_child` escalates to a force-kill.
+pub const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+/// Lock a mutex without panicking on poison. A panic while holding one of
+/// these locks (child/config state, log buffer) shouldn't be allowed to
+/// permanently break every later access to it - that's fatal for the
+/// long-running supervisor thread and a risk on the shutdown path.
+fn lock_recover<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+#[cfg(windows)]
+const CREATE_NEW_PROCESS_GROUP: u32 = 0x00000200;
+
+/// Name of the backend sidecar binary for the current platform.
+#[cfg(target_os = "windows")]
+const SIDECAR_NAME: &str = "das-backend.exe";
+#[cfg(not(target_os = "windows"))]
+const SIDECAR_NAME: &str = "das-backend";
+
+/// Locate the backend sidecar next to the app executable.
+///
+/// Checks the plain sidecar name first, then falls back to the
+/// target-triple-suffixed name Tauri uses for bundled sidecars.
+pub fn resolve_backend_path(exe_dir: &Path) -> Result<PathBuf, io::Error> {
+    let plain_path = exe_dir.join(SIDECAR_NAME);
+    if plain_path.exists() {
+        return Ok(plain_path);
+    }
+
+    #[cfg(target_os = "windows")]
+    let triple_suffixed = exe_dir.join("das-backend-x86_64-pc-windows-msvc.exe");
+    #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
+    let triple_suffixed = exe_dir.join("das-backend-aarch64-apple-darwin");
+    #[cfg(all(target_os = "macos", not(target_arch = "aarch64")))]
+    let triple_suffixed = exe_dir.join("das-backend-x86_64-apple-darwin");
+    #[cfg(target_os = "linux")]
+    let triple_suffixed = exe_dir.join("das-backend-x86_64-unknown-linux-gnu");
+
+    #[cfg(debug_assertions)]
+    println!("Trying sidecar path: {:?}", triple_suffixed);
+
+    if triple_suffixed.exists() {
+        Ok(triple_suffixed)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Backend exe not found at {:?}", plain_path),
+        ))
+    }
+}
+
+/// Apply platform-specific spawn options so the child can later be
+/// terminated gracefully instead of only by a hard kill.
+///
+/// On Windows this puts the child in its own process group so a
+/// `CTRL_BREAK_EVENT` can be targeted at it without also signalling us.
+pub fn prepare_command(command: &mut Command) {
+    #[cfg(windows)]
+    {
+        command.creation_flags(CREATE_NEW_PROCESS_GROUP);
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = command;
+    }
+}
+
+/// Ask the child to shut down gracefully: `SIGTERM` on Unix,
+/// `CTRL_BREAK_EVENT` on Windows. Does not wait for exit.
+#[cfg(unix)]
+pub fn request_graceful_shutdown(child: &Child) -> io::Result<()> {
+    let pid = child.id() as libc::pid_t;
+    let result = unsafe { libc::kill(pid, libc::SIGTERM) };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(windows)]
+pub fn request_graceful_shutdown(child: &Child) -> io::Result<()> {
+    use windows_sys::Win32::System::Console::{
+        AttachConsole, FreeConsole, GenerateConsoleCtrlEvent, CTRL_BREAK_EVENT,
+    };
+
+    // GenerateConsoleCtrlEvent only works for a console the calling process
+    // is itself attached to, and under `windows_subsystem = "windows"` the
+    // packaged app has no console of its own. Detach, attach to the
+    // child's console (its process group id equals its pid, since we
+    // spawned it with CREATE_NEW_PROCESS_GROUP), signal it, then detach
+    // again so we don't hang on to the child's console afterward.
+    unsafe {
+        FreeConsole();
+        if AttachConsole(child.id()) == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let result = GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, child.id());
+        let ctrl_event_err = if result == 0 { Some(io::Error::last_os_error()) } else { None };
+        FreeConsole();
+        match ctrl_event_err {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Force-kill the child: `taskkill /F` on Windows (falling back to
+/// `Child::kill` only if `taskkill` cannot be run), `Child::kill` elsewhere.
+pub fn force_kill(child: &mut Child) {
+    let pid = child.id();
+    #[cfg(windows)]
+    {
+        let result = Command::new("taskkill")
+            .args(&["/PID", &pid.to_string(), "/F"])
+            .output();
+        if result.is_err() {
+            let _ = child.kill();
+        }
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = pid;
+        let _ = child.kill();
+    }
+}
+
+/// Shut the backend down with a timeout escalation ladder: request a
+/// graceful exit, wait up to `grace` for it to actually happen (polled
+/// via `try_wait` on a background thread), and only force-kill once the
+/// grace window elapses. Shared by the close-requested and app-exit
+/// handlers so they can't drift out of sync with each other.
+pub fn shutdown_child(child: Child, grace: Duration) {
+    let child = Arc::new(Mutex::new(child));
+    let watcher = Arc::clone(&child);
+    let (exited_tx, exited_rx) = mpsc::channel();
+
+    thread::spawn(move || loop {
+        let exited = matches!(lock_recover(&watcher).try_wait(), Ok(Some(_)));
+        if exited {
+            let _ = exited_tx.send(());
+            return;
+        }
+        thread::sleep(Duration::from_millis(50));
+    });
+
+    if request_graceful_shutdown(&lock_recover(&child)).is_err() {
+        #[cfg(debug_assertions)]
+        eprintln!("Failed to request graceful shutdown, force-killing backend");
+        force_kill(&mut lock_recover(&child));
+        return;
+    }
+
+    match exited_rx.recv_timeout(grace) {
+        Ok(()) => {
+            #[cfg(debug_assertions)]
+            println!("Backend exited gracefully");
+        }
+        Err(mpsc::RecvTimeoutError::Timeout) | Err(mpsc::RecvTimeoutError::Disconnected) => {
+            #[cfg(debug_assertions)]
+            println!("Backend did not exit within {:?}, force-killing", grace);
+            force_kill(&mut lock_recover(&child));
+        }
+    }
+}
+
+const LOG_BUFFER_CAPACITY: usize = 500;
+
+#[derive(Clone, Copy, Debug, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+/// A single line of backend output, as broadcast via the `backend-log`
+/// event and returned by the `get_backend_logs` command.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct LogLine {
+    pub stream: LogStream,
+    pub text: String,
+    pub ts: u64,
+}
+
+impl LogLine {
+    fn new(stream: LogStream, text: String) -> Self {
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        Self { stream, text, ts }
+    }
+}
+
+/// Ring buffer of recent backend log lines, so a freshly opened log panel
+/// can backfill instead of only seeing lines emitted from now on.
+pub struct LogBuffer(Mutex<VecDeque<LogLine>>);
+
+impl LogBuffer {
+    pub fn new() -> Self {
+        Self(Mutex::new(VecDeque::with_capacity(LOG_BUFFER_CAPACITY)))
+    }
+
+    fn push(&self, line: LogLine) {
+        let mut buffer = lock_recover(&self.0);
+        if buffer.len() == LOG_BUFFER_CAPACITY {
+            buffer.pop_front();
+        }
+        buffer.push_back(line);
+    }
+
+    pub fn recent(&self) -> Vec<LogLine> {
+        lock_recover(&self.0).iter().cloned().collect()
+    }
+}
+
+/// Read lines from a piped child stream, tee them to the log file, buffer
+/// them for backfill, and emit each as a `backend-log` event.
+fn spawn_log_reader<R>(app: AppHandle, reader: R, stream: LogStream, mut tee: File)
+where
+    R: Read + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut reader = BufReader::new(reader);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            // Read raw bytes rather than `read_line`: the backend's output
+            // isn't guaranteed to be valid UTF-8, and `read_line` erroring
+            // out on a bad byte would kill this thread - with nobody left
+            // reading the pipe, the child can then block on its own
+            // stdout/stderr writes once the OS pipe buffer fills.
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) => break,
+                Ok(_) => {
+                    // Strip the trailing newline and the `\r` Windows leaves behind.
+                    let text = String::from_utf8_lossy(&line);
+                    let text = text.trim_end_matches(['\r', '\n']).to_string();
+                    let _ = writeln!(tee, "{}", text);
+
+                    let log_line = LogLine::new(stream, text);
+                    if let Some(buffer) = app.try_state::<LogBuffer>() {
+                        buffer.push(log_line.clone());
+                    }
+                    let _ = app.emit("backend-log", log_line);
+                }
+                Err(_) => break,
+            }
+        }
+    });
+}
+
+/// Wait for the backend to be ready by polling its `/health` endpoint.
+fn wait_for_backend_ready(port: u16, max_attempts: u32, delay_ms: u64) -> bool {
+    // Backend binds to 0.0.0.0 when running as exe, so we connect to 127.0.0.1
+    // which the OS translates to localhost networking
+    let addr = format!("127.0.0.1:{}", port);
+    for _attempt in 0..max_attempts {
+        match TcpStream::connect(&addr) {
+            Ok(mut stream) => {
+                if stream.set_read_timeout(Some(Duration::from_millis(1000))).is_err() {
+                    thread::sleep(Duration::from_millis(delay_ms));
+                    continue;
+                }
+
+                let request = format!("GET /health HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n", addr);
+                if stream.write_all(request.as_bytes()).is_ok() {
+                    let mut response = vec![0u8; 2048];
+                    match stream.read(&mut response) {
+                        Ok(bytes_read) if bytes_read > 0 => {
+                            let response_str = String::from_utf8_lossy(&response[..bytes_read]);
+                            if response_str.contains("HTTP/1.1 200") || response_str.contains("\"status\"") {
+                                #[cfg(debug_assertions)]
+                                println!("Backend is ready!");
+                                return true;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                thread::sleep(Duration::from_millis(delay_ms));
+            }
+            Err(_) => {
+                #[cfg(debug_assertions)]
+                println!("Waiting for backend...");
+                thread::sleep(Duration::from_millis(delay_ms));
+            }
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    eprintln!("Backend readiness check timed out after {} attempts", max_attempts);
+    false
+}
+
+/// Runtime info about the spawned backend, so other commands can reach
+/// it without re-deriving the port or re-reading the `Child`.
+#[derive(Clone, Copy, Debug)]
+pub struct BackendConfig {
+    pub port: u16,
+    pub pid: u32,
+}
+
+/// Bind an ephemeral port on the loopback interface and hand it back for
+/// the backend to listen on. The listener is dropped immediately after;
+/// there's an unavoidable TOCTOU window, but it's the same approach the
+/// OS itself uses for "any free port" (bind to port 0).
+fn allocate_port() -> io::Result<u16> {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    listener.local_addr().map(|addr| addr.port())
+}
+
+/// Spawn the backend sidecar, streaming its stdout/stderr to both the
+/// frontend (as `backend-log` events) and `backend.log` on disk.
+pub fn start_backend(app: &AppHandle) -> Result<(Child, BackendConfig), io::Error> {
+    let exe_path = std::env::current_exe()?;
+    let exe_dir = exe_path
+        .parent()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Cannot find exe directory"))?;
+
+    let backend_exe = resolve_backend_path(exe_dir)?;
+
+    #[cfg(debug_assertions)]
+    println!("Looking for backend at: {:?}", backend_exe);
+
+    let port = allocate_port()?;
+    #[cfg(debug_assertions)]
+    println!("Allocated backend port: {}", port);
+
+    // Keep logs and the backend's working data (e.g. its SQLite database)
+    // in the per-user app-data/app-log dirs, not next to the exe - installs
+    // under Program Files are read-only to non-admin users.
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| io::Error::new(io::ErrorKind::NotFound, e.to_string()))?;
+    let app_log_dir = app
+        .path()
+        .app_log_dir()
+        .map_err(|e| io::Error::new(io::ErrorKind::NotFound, e.to_string()))?;
+    std::fs::create_dir_all(&app_data_dir)?;
+    std::fs::create_dir_all(&app_log_dir)?;
+
+    let log_path = app_log_dir.join("backend.log");
+    let stdout_tee = File::create(&log_path)?;
+    let stderr_tee = stdout_tee.try_clone()?;
+
+    #[cfg(debug_assertions)]
+    println!("Backend log file: {:?}", log_path);
+    #[cfg(debug_assertions)]
+    println!("Backend data dir: {:?}", app_data_dir);
+
+    let mut command = Command::new(&backend_exe);
+    command
+        .current_dir(&app_data_dir)
+        .env("PYTHONIOENCODING", "utf-8")  // Force UTF-8 encoding for Python
+        .env("EDUCORE_PORT", port.to_string())
+        .env("EDUCORE_DATA_DIR", &app_data_dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    prepare_command(&mut command);
+
+    let mut child = command.spawn()?;
+
+    let stdout = child.stdout.take().expect("stdout was piped");
+    let stderr = child.stderr.take().expect("stderr was piped");
+    spawn_log_reader(app.clone(), stdout, LogStream::Stdout, stdout_tee);
+    spawn_log_reader(app.clone(), stderr, LogStream::Stderr, stderr_tee);
+
+    // Wait for backend to be ready (up to 20 seconds, checking every 200ms)
+    // Increased timeout for packaged exe startup complexity and database initialization
+    if !wait_for_backend_ready(port, 100, 200) {
+        #[cfg(debug_assertions)]
+        eprintln!("Warning: Backend may not be ready yet");
+    }
+
+    let config = BackendConfig { port, pid: child.id() };
+    Ok((child, config))
+}
+
+/// `start_backend`, wrapped with the `backend-status` events the frontend
+/// needs to render anything other than silence. Used by both the initial
+/// launch in `main.rs` and the supervisor's restart path so they can't
+/// drift out of sync with each other.
+pub fn start_backend_and_announce(app: &AppHandle) -> Result<(Child, BackendConfig), io::Error> {
+    let _ = app.emit("backend-status", BackendStatus::Starting);
+    let result = start_backend(app);
+    if let Ok((_, config)) = &result {
+        let _ = app.emit("backend-status", BackendStatus::Ready { port: config.port });
+    }
+    result
+}
+
+/// Coordinated lifecycle state for the backend: the child process, its
+/// runtime config, and whether we're in the middle of an intentional
+/// shutdown. Replaces separately managed `Option<Child>`/`Option<Config>`
+/// state so the supervisor and the shutdown handlers can't race each other.
+pub struct BackendState {
+    child: Mutex<Option<Child>>,
+    config: Mutex<Option<BackendConfig>>,
+    shutting_down: AtomicBool,
+}
+
+impl BackendState {
+    pub fn new() -> Self {
+        Self {
+            child: Mutex::new(None),
+            config: Mutex::new(None),
+            shutting_down: AtomicBool::new(false),
+        }
+    }
+
+    pub fn set(&self, child: Child, config: BackendConfig) {
+        *lock_recover(&self.child) = Some(child);
+        *lock_recover(&self.config) = Some(config);
+    }
+
+    pub fn endpoint(&self) -> Option<String> {
+        lock_recover(&self.config).map(|cfg| format!("http://127.0.0.1:{}", cfg.port))
+    }
+
+    /// Mark an intentional shutdown in progress (gates the supervisor),
+    /// take the running child if any, and run it through the graceful
+    /// shutdown escalation ladder.
+    pub fn terminate(&self, grace: Duration) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        let child = lock_recover(&self.child).take();
+        if let Some(child) = child {
+            #[cfg(debug_assertions)]
+            println!("Terminating backend process (PID: {})", child.id());
+            shutdown_child(child, grace);
+        }
+    }
+}
+
+/// Status pushed to the frontend as the supervisor watches the backend.
+/// `Ready` carries the port because a supervisor-triggered restart can land
+/// on a different ephemeral port than the one the frontend first fetched
+/// via `get_backend_endpoint` - the event is how it finds out the endpoint
+/// moved.
+#[derive(Clone, Copy, Debug, serde::Serialize)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum BackendStatus {
+    Starting,
+    Ready { port: u16 },
+    Crashed,
+    GivingUp,
+}
+
+const RESTART_BASE_BACKOFF: Duration = Duration::from_millis(200);
+const RESTART_MAX_BACKOFF: Duration = Duration::from_secs(10);
+const MAX_RESTART_ATTEMPTS: u32 = 5;
+
+/// Block until the managed child exits, returning `true`, or until an
+/// intentional shutdown is flagged, returning `false`. A missing child
+/// (e.g. the previous restart attempt failed to spawn one) counts as
+/// "exited" so the caller retries immediately.
+fn wait_for_exit_or_shutdown(app: &AppHandle) -> bool {
+    loop {
+        thread::sleep(Duration::from_millis(300));
+        let state = app.state::<BackendState>();
+        if state.shutting_down.load(Ordering::SeqCst) {
+            return false;
+        }
+        let exited = match lock_recover(&state.child).as_mut() {
+            Some(child) => matches!(child.try_wait(), Ok(Some(_))),
+            None => true,
+        };
+        if exited {
+            return true;
+        }
+    }
+}
+
+/// Watch the backend and restart it with exponential backoff if it dies
+/// unexpectedly while the app is still running. Gated on `shutting_down`
+/// so an intentional close/exit never looks like a crash.
+pub fn spawn_supervisor(app: AppHandle) {
+    thread::spawn(move || {
+        // A backend that stays up this long before dying starts a fresh
+        // round of restarts; quicker deaths count towards the cutoff.
+        const STABLE_UPTIME: Duration = Duration::from_secs(60);
+        let mut attempt: u32 = 0;
+        let mut last_start = std::time::Instant::now();
+        loop {
+            if !wait_for_exit_or_shutdown(&app) {
+                return;
+            }
+
+            let state = app.state::<BackendState>();
+            if state.shutting_down.load(Ordering::SeqCst) {
+                return;
+            }
+            *lock_recover(&state.child) = None;
+            if last_start.elapsed() >= STABLE_UPTIME {
+                attempt = 0;
+            }
+
+            if attempt >= MAX_RESTART_ATTEMPTS {
+                #[cfg(debug_assertions)]
+                eprintln!("Backend crashed {} times, giving up", attempt);
+                let _ = app.emit("backend-status", BackendStatus::GivingUp);
+                return;
+            }
+
+            #[cfg(debug_assertions)]
+            eprintln!("Backend crashed unexpectedly, restarting (attempt {})", attempt + 1);
+            let _ = app.emit("backend-status", BackendStatus::Crashed);
+
+            let backoff = RESTART_BASE_BACKOFF
+                .saturating_mul(1 << attempt.min(6))
+                .min(RESTART_MAX_BACKOFF);
+            thread::sleep(backoff);
+            attempt += 1;
+
+            match start_backend_and_announce(&app) {
+                Ok((child, config)) => {
+                    state.set(child, config);
+                }
+                Err(e) => {
+                    #[cfg(debug_assertions)]
+                    eprintln!("Backend restart attempt failed: {}", e);
+                }
+            }
+            last_start = std::time::Instant::now();
+        }
+    });
+}
@@ -6,13 +6,8 @@
 
 use tauri::{Manager, command};
 use tauri::webview::Color;
-use std::sync::Mutex;
-use std::process::{Command, Child, Stdio};
-use std::fs::File;
-use std::time::Duration;
-use std::net::TcpStream;
-use std::io::{Write, Read};
-use std::thread;
+
+mod backend;
 
 // Command to handle search functionality
 #[command]
@@ -54,108 +49,26 @@ async fn open_settings(_window: tauri::Window) -> Result<String, String> {
     Ok("Settings opened".to_string())
 }
 
-// Backend process state
-struct BackendProcess(Mutex<Option<Child>>);
+// Command to backfill the backend log panel with recently buffered lines
+#[command]
+async fn get_backend_logs(log_buffer: tauri::State<'_, backend::LogBuffer>) -> Result<Vec<backend::LogLine>, String> {
+    Ok(log_buffer.recent())
+}
 
-fn wait_for_backend_ready(max_attempts: u32, delay_ms: u64) -> bool {
-    // Wait for backend to be ready by checking the /health endpoint
-    // Backend binds to 0.0.0.0 when running as exe, so we connect to 127.0.0.1
-    // which the OS translates to localhost networking
-    for _attempt in 0..max_attempts {
-        match TcpStream::connect("127.0.0.1:8000") {
-            Ok(mut stream) => {
-                // Set a short timeout for the read operation
-                if let Err(_) = stream.set_read_timeout(Some(Duration::from_millis(1000))) {
-                    thread::sleep(Duration::from_millis(delay_ms));
-                    continue;
-                }
-                
-                // Send HTTP GET request to /health endpoint
-                let request = "GET /health HTTP/1.1\r\nHost: 127.0.0.1:8000\r\nConnection: close\r\n\r\n";
-                if stream.write_all(request.as_bytes()).is_ok() {
-                    let mut response = vec![0u8; 2048];
-                    match stream.read(&mut response) {
-                        Ok(bytes_read) if bytes_read > 0 => {
-                            let response_str = String::from_utf8_lossy(&response[..bytes_read]);
-                            // Check for successful HTTP response with health check
-                            if response_str.contains("HTTP/1.1 200") || response_str.contains("\"status\"") {
-                                #[cfg(debug_assertions)]
-                                println!("Backend is ready!");
-                                return true;
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-                thread::sleep(Duration::from_millis(delay_ms));
-            }
-            Err(_) => {
-                // TCP connection failed, backend not yet listening
-                #[cfg(debug_assertions)]
-                println!("Waiting for backend...");
-                thread::sleep(Duration::from_millis(delay_ms));
-            }
-        }
-    }
-    
-    #[cfg(debug_assertions)]
-    eprintln!("Backend readiness check timed out after {} attempts", max_attempts);
-    false
+// Command so the frontend can fetch the backend's actual base URL instead
+// of hardcoding a port
+#[command]
+async fn get_backend_endpoint(state: tauri::State<'_, backend::BackendState>) -> Result<String, String> {
+    state.endpoint().ok_or_else(|| "Backend is not running".to_string())
 }
 
-fn start_backend() -> Result<Child, std::io::Error> {
-    // Get the directory where the exe is located
-    let exe_path = std::env::current_exe()?;
-    let exe_dir = exe_path.parent().ok_or_else(|| {
-        std::io::Error::new(std::io::ErrorKind::NotFound, "Cannot find exe directory")
-    })?;
-    
-    // Look for backend exe in the same directory
-    let backend_path = exe_dir.join("das-backend.exe");
-    
-    #[cfg(debug_assertions)]
-    println!("Looking for backend at: {:?}", backend_path);
-    
-    // Create log file for backend output
-    let log_path = exe_dir.join("backend.log");
-    let log_file = File::create(&log_path)?;
-    let log_file_err = log_file.try_clone()?;
-    
-    #[cfg(debug_assertions)]
-    println!("Backend log file: {:?}", log_path);
-    
-    let backend_exe = if backend_path.exists() {
-        backend_path
-    } else {
-        // Try the sidecar naming convention
-        let sidecar_path = exe_dir.join("das-backend-x86_64-pc-windows-msvc.exe");
-        #[cfg(debug_assertions)]
-        println!("Trying sidecar path: {:?}", sidecar_path);
-        if sidecar_path.exists() {
-            sidecar_path
-        } else {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                format!("Backend exe not found at {:?}", backend_path)
-            ));
-        }
-    };
-    
-    let child = Command::new(&backend_exe)
-        .current_dir(exe_dir)
-        .env("PYTHONIOENCODING", "utf-8")  // Force UTF-8 encoding for Python
-        .stdout(Stdio::from(log_file))
-        .stderr(Stdio::from(log_file_err))
-        .spawn()?;
-    
-    // Wait for backend to be ready (up to 20 seconds, checking every 200ms)
-    // Increased timeout for packaged exe startup complexity and database initialization
-    if !wait_for_backend_ready(100, 200) {
-        #[cfg(debug_assertions)]
-        eprintln!("Warning: Backend may not be ready yet");
+/// Terminate the backend through its coordinated lifecycle state. Shared
+/// by the close-requested and app-exit handlers so they can't drift out
+/// of sync with each other.
+fn terminate_backend(app_handle: &tauri::AppHandle) {
+    if let Some(state) = app_handle.try_state::<backend::BackendState>() {
+        state.terminate(backend::SHUTDOWN_GRACE);
     }
-    
-    Ok(child)
 }
 
 fn main() {
@@ -163,23 +76,32 @@ fn main() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_window_state::Builder::default().build())
-        .invoke_handler(tauri::generate_handler![handle_search, toggle_theme, open_settings])
+        .invoke_handler(tauri::generate_handler![handle_search, toggle_theme, open_settings, get_backend_logs, get_backend_endpoint])
         .setup(|app| {
-            // Start the backend server
-            match start_backend() {
-                Ok(child) => {
+            // Buffer recent backend log lines so a freshly opened log panel can backfill
+            app.manage(backend::LogBuffer::new());
+            app.manage(backend::BackendState::new());
+
+            // Start the backend server, emitting the same backend-status
+            // events the supervisor's restart path emits, so the frontend
+            // has a signal for the common case (first launch) too, not
+            // just post-crash.
+            match backend::start_backend_and_announce(app.handle()) {
+                Ok((child, config)) => {
                     #[cfg(debug_assertions)]
-                    println!("Backend server started with PID: {}", child.id());
-                    app.manage(BackendProcess(Mutex::new(Some(child))));
+                    println!("Backend server started with PID: {} on port {}", config.pid, config.port);
+                    app.state::<backend::BackendState>().set(child, config);
                 }
                 Err(e) => {
                     #[cfg(debug_assertions)]
                     eprintln!("Failed to start backend: {}", e);
                     #[cfg(not(debug_assertions))]
                     let _ = e; // Suppress unused variable warning in release builds
-                    app.manage(BackendProcess(Mutex::new(None)));
                 }
             }
+
+            // Watch the backend and restart it with backoff if it crashes
+            backend::spawn_supervisor(app.handle().clone());
             
             // Get main window - using standard Windows decorations (no overlay titlebar)
             // This gives us native Windows title bar with proper minimize/maximize/close buttons
@@ -198,24 +120,20 @@ fn main() {
                 if let tauri::WindowEvent::CloseRequested { api, .. } = event {
                     // Allow the window to close
                     api.prevent_close();
-                    
-                    // Terminate backend before exiting
-                    if let Some(backend) = app_handle_clone.try_state::<BackendProcess>() {
-                        if let Ok(mut process) = backend.0.lock() {
-                            if let Some(mut child) = process.take() {
-                                let pid = child.id();
-                                #[cfg(debug_assertions)]
-                                println!("Window close detected, terminating backend (PID: {})", pid);
-                                
-                                let _ = Command::new("taskkill")
-                                    .args(&["/PID", &pid.to_string(), "/F"])
-                                    .output();
-                            }
-                        }
-                    }
-                    
-                    // Now exit the app
-                    std::process::exit(0);
+
+                    // Terminate the backend on a background thread - this
+                    // callback runs on the platform event loop, and
+                    // shutdown_child's grace-period wait can take up to
+                    // SHUTDOWN_GRACE. Blocking here instead would stop the
+                    // event loop pumping messages and show the window as
+                    // "Not Responding" for the whole wait.
+                    let app_handle_for_shutdown = app_handle_clone.clone();
+                    std::thread::spawn(move || {
+                        #[cfg(debug_assertions)]
+                        println!("Window close detected, terminating backend");
+                        terminate_backend(&app_handle_for_shutdown);
+                        app_handle_for_shutdown.exit(0);
+                    });
                 }
             });
             
@@ -225,42 +143,13 @@ fn main() {
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
         .run(|app_handle, event| {
-            // Handle app exit to cleanup backend process
+            // Handle app exit to cleanup backend process. By the time this
+            // fires every window is already gone, so there's no message
+            // pump left to stall; in the common path the close-requested
+            // handler above has already terminated the backend before
+            // calling app_handle.exit(), making this a fast no-op.
             if let tauri::RunEvent::Exit = event {
-                if let Some(backend) = app_handle.try_state::<BackendProcess>() {
-                    if let Ok(mut process) = backend.0.lock() {
-                        if let Some(mut child) = process.take() {
-                            // Get process ID for termination
-                            let pid = child.id();
-                            
-                            #[cfg(debug_assertions)]
-                            println!("Terminating backend process (PID: {})", pid);
-                            
-                            // Use taskkill to forcefully terminate the backend process
-                            // This ensures the backend exits even if it's not responsive
-                            match Command::new("taskkill")
-                                .args(&["/PID", &pid.to_string(), "/F"])
-                                .output() {
-                                Ok(output) => {
-                                    #[cfg(debug_assertions)]
-                                    {
-                                        if output.status.success() {
-                                            println!("Backend process (PID: {}) terminated successfully", pid);
-                                        } else {
-                                            eprintln!("taskkill returned: {}", String::from_utf8_lossy(&output.stderr));
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    // Also try the regular kill method as a fallback
-                                    #[cfg(debug_assertions)]
-                                    eprintln!("taskkill failed: {}", e);
-                                    let _ = child.kill();
-                                }
-                            }
-                        }
-                    }
-                }
+                terminate_backend(app_handle);
             }
         });
 }
\ No newline at end of file